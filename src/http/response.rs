@@ -1,6 +1,10 @@
-use std::io::Read;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::time::UNIX_EPOCH;
 use std::{fmt::Display, io};
 
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::Compression;
+use pulldown_cmark::{html, Event, Options, Parser};
 use url_escape::{decode, encode, NON_ALPHANUMERIC};
 
 use super::request::{HttpRequest, Version};
@@ -11,11 +15,54 @@ pub struct HttpResponse {
     status: ResponseStatus,
     content_length: usize,
     accept_ranges: AcceptRanges,
-    pub response_body: Vec<u8>,
+    pub header: Vec<u8>,
+    pub response_body: ResponseBody,
     pub current_path: String,
     pub content_type: String,
 }
 
+/// The body of a response, produced lazily so that serving a large file
+/// doesn't require buffering it into memory up front.
+#[derive(Debug)]
+pub enum ResponseBody {
+    /// A body that already lives in memory (directory listings, error pages).
+    Bytes(Vec<u8>),
+    /// An open file plus how many bytes of it are left to stream, read out in
+    /// fixed-size chunks as the socket drains.
+    File {
+        file: std::fs::File,
+        remaining: u64,
+    },
+}
+
+impl ResponseBody {
+    /// Size of each chunk pulled from a streamed file.
+    pub const CHUNK_SIZE: usize = 64 * 1024;
+
+    /// Pulls the next chunk of the body, or `None` once it is exhausted.
+    pub fn next_chunk(&mut self) -> io::Result<Option<Vec<u8>>> {
+        match self {
+            ResponseBody::Bytes(bytes) => {
+                if bytes.is_empty() {
+                    Ok(None)
+                } else {
+                    Ok(Some(std::mem::take(bytes)))
+                }
+            }
+            ResponseBody::File { file, remaining } => {
+                if *remaining == 0 {
+                    return Ok(None);
+                }
+                let take = Self::CHUNK_SIZE.min(*remaining as usize);
+                let mut buf = vec![0u8; take];
+                file.read_exact(&mut buf)?;
+                *remaining -= take as u64;
+                Ok(Some(buf))
+            }
+        }
+    }
+}
+
 impl HttpResponse {
     pub fn new(request: &HttpRequest) -> io::Result<HttpResponse> {
         let version = Version::V1_1;
@@ -24,8 +71,19 @@ impl HttpResponse {
         let mut content_type = "text/html".to_string();
         let mut accept_ranges = AcceptRanges::None;
         let resource_path = request.resource.path.clone();
+        let (resource_path, query) = match resource_path.split_once('?') {
+            Some((path, query)) => (path.to_string(), Some(query.to_string())),
+            None => (resource_path, None),
+        };
         let current_path = decode(&resource_path).into_owned();
         let trimmed_path = current_path.trim_start_matches('/');
+        let wants_json = header_value(&request.headers, "accept")
+            .map(|accept| accept.contains("application/json"))
+            .unwrap_or(false)
+            || query
+                .as_deref()
+                .map(|query| query.split('&').any(|param| param == "format=json"))
+                .unwrap_or(false);
         let rootcwd = std::env::current_dir()?;
         let rootcwd_canonical = rootcwd.canonicalize()?;
         let new_path = rootcwd.join(trimmed_path);
@@ -35,10 +93,75 @@ impl HttpResponse {
         let rootcwd_len = rootcwd_canonical.components().count();
         let new_path_len = new_path_canonical.components().count();
 
-        let mut response_body = Vec::new();
+        let mut header = Vec::new();
+        let mut response_body = ResponseBody::Bytes(Vec::new());
+
+        let raw_requested = query
+            .as_deref()
+            .map(|query| query.split('&').any(|param| param == "raw=1"))
+            .unwrap_or(false);
+        let accepts_html = header_value(&request.headers, "accept")
+            .map(|accept| accept.contains("text/html") || accept.contains("*/*"))
+            .unwrap_or(true);
+        let is_markdown = new_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.eq_ignore_ascii_case("md") || extension.eq_ignore_ascii_case("markdown"))
+            .unwrap_or(false);
 
         if new_path.exists() {
-            if new_path.is_file() {
+            if new_path.is_file() && is_markdown && !raw_requested && accepts_html {
+                let metadata = std::fs::metadata(&new_path)?;
+                let file_len = metadata.len();
+                let mtime_secs = metadata
+                    .modified()?
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                let last_modified = format_http_date(mtime_secs);
+                let etag = format!("W/\"{}-{}\"", mtime_secs, file_len);
+                status = ResponseStatus::OK;
+                content_type = "text/html".to_string();
+                accept_ranges = AcceptRanges::None;
+
+                if is_not_modified(&request.headers, &etag, mtime_secs) {
+                    status = ResponseStatus::NotModified;
+                    content_length = 0;
+                    let header_line = format!(
+                        "{} {}\n{}\nlast-modified: {}\netag: {}\ncontent-length: 0\r\n\r\n",
+                        version, status, accept_ranges, last_modified, etag
+                    );
+                    header.extend_from_slice(header_line.as_bytes());
+                    response_body = ResponseBody::Bytes(Vec::new());
+                } else {
+                    let source = std::fs::read_to_string(&new_path)?;
+                    let body_html = render_markdown(&source);
+                    let title = new_path
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .unwrap_or("");
+                    let page = wrap_markdown_page(title, &body_html);
+
+                    let content_encoding = header_value(&request.headers, "accept-encoding")
+                        .and_then(negotiate_encoding);
+                    let page = match content_encoding {
+                        Some(encoding) => compress(&page, encoding)?,
+                        None => page,
+                    };
+                    content_length = page.len();
+
+                    let mut header_line = format!(
+                        "{} {}\n{}\ncontent-type: {}\ncontent-length: {}\nlast-modified: {}\netag: {}",
+                        version, status, accept_ranges, content_type, content_length, last_modified, etag
+                    );
+                    if let Some(encoding) = content_encoding {
+                        header_line.push_str(&format!("\ncontent-encoding: {}", encoding));
+                    }
+                    header_line.push_str("\nvary: accept-encoding\r\n\r\n");
+                    header.extend_from_slice(header_line.as_bytes());
+                    response_body = ResponseBody::Bytes(page);
+                }
+            } else if new_path.is_file() {
                 let file_type_result = infer::get_from_path(&new_path)?;
                 if let Some(file_type) = file_type_result {
                     content_type = file_type.mime_type().to_string();
@@ -46,71 +169,130 @@ impl HttpResponse {
                     content_type = "text/plain".to_string();
                 }
 
-                // Read file as binary
                 let mut file = std::fs::File::open(&new_path)?;
-                let mut content = Vec::new();
-                file.read_to_end(&mut content)?;
-                content_length = content.len();
+                let metadata = file.metadata()?;
+                let file_len = metadata.len();
+                let mtime_secs = metadata
+                    .modified()?
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                let last_modified = format_http_date(mtime_secs);
+                let etag = format!("W/\"{}-{}\"", mtime_secs, file_len);
                 status = ResponseStatus::OK;
                 accept_ranges = AcceptRanges::Bytes;
 
-                // Create the response header
-                let header = format!(
-                    "{} {}\n{}\ncontent-type: {}\ncontent-length: {}\r\n\r\n",
-                    version, status, accept_ranges, content_type, content_length
-                );
-                response_body.extend_from_slice(header.as_bytes());
-                response_body.extend_from_slice(&content);
+                let not_modified = is_not_modified(&request.headers, &etag, mtime_secs);
+
+                if not_modified {
+                    status = ResponseStatus::NotModified;
+                    content_length = 0;
+                    let header_line = format!(
+                        "{} {}\n{}\nlast-modified: {}\netag: {}\ncontent-length: 0\r\n\r\n",
+                        version, status, accept_ranges, last_modified, etag
+                    );
+                    header.extend_from_slice(header_line.as_bytes());
+                    response_body = ResponseBody::Bytes(Vec::new());
+                } else {
+                    // Serve a byte-range slice if the client asked for one
+                    let mut content_range: Option<String> = None;
+                    let mut offset = 0u64;
+                    let mut remaining = file_len;
+                    let mut unsatisfiable = false;
+                    if let Some(range_value) = header_value(&request.headers, "range") {
+                        match parse_range(range_value, file_len) {
+                            Ok(Some((start, end))) => {
+                                status = ResponseStatus::PartialContent;
+                                content_range = Some(format!("{}-{}/{}", start, end, file_len));
+                                offset = start;
+                                remaining = end - start + 1;
+                            }
+                            Ok(None) => {}
+                            Err(()) => {
+                                status = ResponseStatus::RangeNotSatisfiable;
+                                content_range = Some(format!("*/{}", file_len));
+                                unsatisfiable = true;
+                            }
+                        }
+                    }
+                    content_length = if unsatisfiable { 0 } else { remaining as usize };
+
+                    // Negotiate compression for compressible, non-range bodies. Above
+                    // MAX_COMPRESSIBLE_FILE_SIZE we skip it and keep streaming from disk
+                    // instead — compressing would mean buffering the whole file in RAM,
+                    // the exact double-allocation that streaming via ResponseBody::File avoids.
+                    let negotiable = !unsatisfiable
+                        && content_range.is_none()
+                        && is_compressible(&content_type)
+                        && file_len <= MAX_COMPRESSIBLE_FILE_SIZE;
+                    let content_encoding = negotiable
+                        .then(|| header_value(&request.headers, "accept-encoding"))
+                        .flatten()
+                        .and_then(negotiate_encoding);
+
+                    response_body = if unsatisfiable {
+                        ResponseBody::Bytes(Vec::new())
+                    } else if let Some(encoding) = content_encoding {
+                        let mut raw = Vec::new();
+                        file.read_to_end(&mut raw)?;
+                        let compressed = compress(&raw, encoding)?;
+                        content_length = compressed.len();
+                        ResponseBody::Bytes(compressed)
+                    } else {
+                        if offset > 0 {
+                            file.seek(SeekFrom::Start(offset))?;
+                        }
+                        ResponseBody::File { file, remaining }
+                    };
+
+                    // Create the response header, written up front
+                    let mut header_line = format!(
+                        "{} {}\n{}\ncontent-type: {}\ncontent-length: {}\nlast-modified: {}\netag: {}",
+                        version, status, accept_ranges, content_type, content_length, last_modified, etag
+                    );
+                    if let Some(content_range) = content_range {
+                        header_line.push_str(&format!("\ncontent-range: bytes {}", content_range));
+                    }
+                    if let Some(encoding) = content_encoding {
+                        header_line.push_str(&format!("\ncontent-encoding: {}", encoding));
+                    }
+                    if negotiable {
+                        header_line.push_str("\nvary: accept-encoding");
+                    }
+                    header_line.push_str("\r\n\r\n");
+                    header.extend_from_slice(header_line.as_bytes());
+                }
             } else if new_path.is_dir() {
                 status = ResponseStatus::OK;
                 accept_ranges = AcceptRanges::None;
 
-                let mut listing = Vec::new();
-                listing.extend_from_slice(b"<html><head><meta charset=\"utf-8\"/></head><body>");
-
-                // Display the current directory
-                let current_dir_display = to_unix_style(new_path.to_str().unwrap_or(""));
-                listing.extend_from_slice(b"<h1>Directory Listing</h1>");
-                listing.extend_from_slice(b"<p>Current directory: ");
-                listing.extend_from_slice(current_dir_display.as_bytes());
-                listing.extend_from_slice(b"</p>");
-
-                // Option to go up one directory shown only if not at root directory
-                if rootcwd_canonical != new_path_canonical {
-                    let parent_path = new_path.parent().unwrap_or(&rootcwd).to_path_buf();
-                    let parent_path_str = parent_path.to_str().unwrap_or("");
-                    let parent_encoded = encode(parent_path_str, NON_ALPHANUMERIC).into_owned();
-                    listing.extend_from_slice(b"<p><a href=\"");
-                    listing.extend_from_slice(parent_encoded.as_bytes());
-                    listing.extend_from_slice(b"\">Up One Level</a></p>");
-                }
+                let mut entries = collect_dir_entries(&new_path, &current_path)?;
+                sort_dir_entries(&mut entries);
 
-                listing.extend_from_slice(b"<ul>");
-                for entry in std::fs::read_dir(&new_path)? {
-                    let entry = entry?;
-                    let file_name = entry.file_name();
-                    let file_name_str = file_name.to_str().expect("invalid unicode");
-                    let full_path = format!("{}/{}", current_path, file_name_str);
-                    let file_name_bytes = file_name.as_encoded_bytes();
-                    let encoded_path = encode(&full_path, NON_ALPHANUMERIC).into_owned();
-
-                    listing.extend_from_slice(b"<li><a href=\"");
-                    listing.extend_from_slice(encoded_path.as_bytes());
-                    listing.extend_from_slice(b"\">");
-                    listing.extend_from_slice(&file_name_bytes);
-                    listing.extend_from_slice(b"</a></li>");
-                }
+                let listing = if wants_json {
+                    content_type = "application/json".to_string();
+                    render_dir_listing_json(&entries)
+                } else {
+                    let current_dir_display = to_unix_style(new_path.to_str().unwrap_or(""));
+                    let parent_href = if rootcwd_canonical != new_path_canonical {
+                        let parent_path = new_path.parent().unwrap_or(&rootcwd).to_path_buf();
+                        let parent_path_str = parent_path.to_str().unwrap_or("");
+                        Some(encode(parent_path_str, NON_ALPHANUMERIC).into_owned())
+                    } else {
+                        None
+                    };
+                    render_dir_listing_html(&current_dir_display, parent_href.as_deref(), &entries)
+                };
 
-                listing.extend_from_slice(b"</ul></body></html>");
                 content_length = listing.len();
-                response_body.extend_from_slice(
+                header.extend_from_slice(
                     format!(
                         "{} {}\n{}\ncontent-type: {}\ncontent-length: {}\r\n\r\n",
                         version, status, accept_ranges, content_type, content_length
                     )
                     .as_bytes(),
                 );
-                response_body.extend_from_slice(&listing);
+                response_body = ResponseBody::Bytes(listing);
             } else {
                 // Handle 404 not found
                 let four_o_four = "
@@ -120,11 +302,14 @@ impl HttpResponse {
                 </body>
                 </html>";
                 content_length = four_o_four.len();
-                let content = format!(
-                    "{} {}\n{}\ncontent-type: {}\ncontent-length: {}\r\n\r\n{}",
-                    version, status, accept_ranges, content_type, content_length, four_o_four
+                header.extend_from_slice(
+                    format!(
+                        "{} {}\n{}\ncontent-type: {}\ncontent-length: {}\r\n\r\n",
+                        version, status, accept_ranges, content_type, content_length
+                    )
+                    .as_bytes(),
                 );
-                response_body.extend_from_slice(content.as_bytes());
+                response_body = ResponseBody::Bytes(four_o_four.as_bytes().to_vec());
             }
         }
 
@@ -134,27 +319,263 @@ impl HttpResponse {
             content_length,
             content_type,
             accept_ranges,
+            header,
             response_body,
             current_path,
         })
     }
 }
+
+/// Looks up a header by name, ignoring ASCII case, since clients are free to
+/// send `Range`, `range`, or any other casing.
+fn header_value<'a>(
+    headers: &'a std::collections::HashMap<String, String>,
+    name: &str,
+) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Decides whether a conditional GET should short-circuit to `304 Not
+/// Modified`. Per RFC 7232 §3.3, when both validators are present on a GET,
+/// the strong/entity validator (`If-None-Match`) takes precedence and
+/// `If-Modified-Since` is ignored.
+fn is_not_modified(
+    headers: &std::collections::HashMap<String, String>,
+    etag: &str,
+    mtime_secs: u64,
+) -> bool {
+    match header_value(headers, "if-none-match") {
+        Some(value) => value.trim() == etag || value.trim() == "*",
+        None => header_value(headers, "if-modified-since")
+            .and_then(parse_http_date)
+            .map(|since_secs| mtime_secs <= since_secs)
+            .unwrap_or(false),
+    }
+}
+
+/// Parses a `Range` header value against a file of length `file_len`.
+///
+/// Supports the three standard forms: `bytes=START-END`, `bytes=START-` and
+/// `bytes=-SUFFIX`. Returns `Ok(None)` when the header isn't a byte-range we
+/// understand (the caller should then serve the full body), `Ok(Some((start,
+/// end)))` with an inclusive range clamped to `file_len - 1`, or `Err(())`
+/// when the range is unsatisfiable.
+fn parse_range(header_value: &str, file_len: u64) -> Result<Option<(u64, u64)>, ()> {
+    let spec = match header_value.trim().strip_prefix("bytes=") {
+        Some(spec) => spec,
+        None => return Ok(None),
+    };
+
+    let (start_str, end_str) = spec.split_once('-').ok_or(())?;
+
+    if start_str.is_empty() {
+        // bytes=-SUFFIX : the last SUFFIX bytes of the file
+        let suffix: u64 = end_str.parse().map_err(|_| ())?;
+        if suffix == 0 || file_len == 0 {
+            return Err(());
+        }
+        let start = file_len.saturating_sub(suffix);
+        return Ok(Some((start, file_len - 1)));
+    }
+
+    let start: u64 = start_str.parse().map_err(|_| ())?;
+    if start >= file_len {
+        return Err(());
+    }
+
+    let end = if end_str.is_empty() {
+        file_len - 1
+    } else {
+        end_str.parse::<u64>().map_err(|_| ())?.min(file_len - 1)
+    };
+
+    if start > end {
+        return Err(());
+    }
+
+    Ok(Some((start, end)))
+}
+
+/// Content types worth gzip/deflate compressing; everything else (images,
+/// video, archives) is either already compressed or not worth the CPU.
+const COMPRESSIBLE_TYPES: [&str; 5] = [
+    "text/html",
+    "text/plain",
+    "application/json",
+    "text/css",
+    "application/javascript",
+];
+
+/// Files larger than this are served uncompressed (streamed straight from
+/// disk) rather than buffered fully into memory to run through the encoder.
+const MAX_COMPRESSIBLE_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+fn is_compressible(content_type: &str) -> bool {
+    COMPRESSIBLE_TYPES.contains(&content_type)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ContentEncoding {
+    Gzip,
+    Deflate,
+}
+
+impl Display for ContentEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg = match self {
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Deflate => "deflate",
+        };
+        write!(f, "{}", msg)
+    }
+}
+
+/// Returns `true` if the given coding (e.g. `"gzip"`) is listed in an
+/// `Accept-Encoding` value and not explicitly rejected with `q=0` (RFC 7231
+/// §5.3.1 — a `q=0` coding is not acceptable to the client).
+fn encoding_is_acceptable(offered: &[String], coding: &str) -> bool {
+    offered.iter().any(|entry| {
+        let mut parts = entry.split(';');
+        if parts.next().unwrap_or("").trim() != coding {
+            return false;
+        }
+        let q: f32 = parts
+            .find_map(|param| param.trim().strip_prefix("q="))
+            .and_then(|q| q.trim().parse().ok())
+            .unwrap_or(1.0);
+        q > 0.0
+    })
+}
+
+/// Picks an encoding from an `Accept-Encoding` header, preferring gzip over
+/// deflate and falling back to no compression (`None`) if neither is offered
+/// (or both are rejected via `q=0`).
+fn negotiate_encoding(accept_encoding: &str) -> Option<ContentEncoding> {
+    let offered: Vec<String> = accept_encoding
+        .split(',')
+        .map(|e| e.trim().to_ascii_lowercase())
+        .collect();
+
+    if encoding_is_acceptable(&offered, "gzip") {
+        Some(ContentEncoding::Gzip)
+    } else if encoding_is_acceptable(&offered, "deflate") {
+        Some(ContentEncoding::Deflate)
+    } else {
+        None
+    }
+}
+
+fn compress(data: &[u8], encoding: ContentEncoding) -> io::Result<Vec<u8>> {
+    match encoding {
+        ContentEncoding::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+        ContentEncoding::Deflate => {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data)?;
+            encoder.finish()
+        }
+    }
+}
+
 #[derive(Debug)]
 enum ResponseStatus {
     OK = 200,
+    PartialContent = 206,
+    NotModified = 304,
     NotFound = 404,
+    RangeNotSatisfiable = 416,
 }
 
 impl Display for ResponseStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let msg = match self {
             ResponseStatus::OK => "200 OK",
+            ResponseStatus::PartialContent => "206 PARTIAL CONTENT",
+            ResponseStatus::NotModified => "304 NOT MODIFIED",
             ResponseStatus::NotFound => "404 NOT FOUND",
+            ResponseStatus::RangeNotSatisfiable => "416 RANGE NOT SATISFIABLE",
         };
         write!(f, "{}", msg)
     }
 }
 
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Converts a proleptic Gregorian civil date into a day count since the Unix
+/// epoch (Howard Hinnant's `days_from_civil` algorithm).
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// The inverse of [`days_from_civil`].
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let y = yoe + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = if m <= 2 { y + 1 } else { y };
+    (y, m as u32, d)
+}
+
+/// Formats a Unix timestamp as an RFC 7231 IMF-fixdate, e.g.
+/// `Sun, 06 Nov 1994 08:49:37 GMT`.
+fn format_http_date(unix_secs: u64) -> String {
+    let unix_secs = unix_secs as i64;
+    let days = unix_secs.div_euclid(86400);
+    let secs_of_day = unix_secs.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(days);
+    let weekday = (((days % 7) + 11) % 7) as usize; // 1970-01-01 (days=0) was a Thursday
+    format!(
+        "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[weekday],
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        secs_of_day / 3600,
+        (secs_of_day % 3600) / 60,
+        secs_of_day % 60,
+    )
+}
+
+/// Parses an RFC 7231 IMF-fixdate (the format we emit for `Last-Modified`)
+/// back into a Unix timestamp, for comparing against `If-Modified-Since`.
+fn parse_http_date(value: &str) -> Option<u64> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let [_, day, month, year, time, _] = parts[..] else {
+        return None;
+    };
+    let day: i64 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as i64 + 1;
+    let year: i64 = year.parse().ok()?;
+    let mut time_parts = time.split(':');
+    let hour: i64 = time_parts.next()?.parse().ok()?;
+    let minute: i64 = time_parts.next()?.parse().ok()?;
+    let second: i64 = time_parts.next()?.parse().ok()?;
+
+    let secs = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second;
+    u64::try_from(secs).ok()
+}
+
 #[derive(Debug)]
 enum AcceptRanges {
     Bytes,
@@ -175,3 +596,448 @@ impl Display for AcceptRanges {
 fn to_unix_style(path: &str) -> String {
     path.replace("\\", "/")
 }
+
+/// Renders Markdown source to an HTML fragment (headings, lists, code
+/// blocks, links, tables, strikethrough).
+fn render_markdown(source: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    // Any file under the served root can reach this path, not just content
+    // the operator authored, so raw HTML in the source (`Event::Html` /
+    // `Event::InlineHtml`) is stripped rather than passed through verbatim —
+    // otherwise a `.md` file containing `<script>` would be a stored-XSS
+    // vector for anyone browsing the directory.
+    let parser = Parser::new_ext(source, options)
+        .filter(|event| !matches!(event, Event::Html(_) | Event::InlineHtml(_)));
+    let mut body_html = String::new();
+    html::push_html(&mut body_html, parser);
+    body_html
+}
+
+/// Wraps a rendered Markdown fragment in a minimal styled page.
+fn wrap_markdown_page(title: &str, body_html: &str) -> Vec<u8> {
+    format!(
+        "<html><head><meta charset=\"utf-8\"/><title>{}</title><style>\
+body {{ font-family: sans-serif; max-width: 800px; margin: 2rem auto; padding: 0 1rem; line-height: 1.6; }}\
+pre {{ background: #f4f4f4; padding: 1rem; overflow-x: auto; }}\
+code {{ background: #f4f4f4; padding: 0.1em 0.3em; }}\
+</style></head><body>{}</body></html>",
+        html_escape(title),
+        body_html
+    )
+    .into_bytes()
+}
+
+/// A single entry in a directory listing, gathered once and rendered as
+/// either HTML or JSON depending on what the client asked for.
+struct DirEntryInfo {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    href: String,
+}
+
+fn collect_dir_entries(dir: &std::path::Path, current_path: &str) -> io::Result<Vec<DirEntryInfo>> {
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let name = file_name.to_str().expect("invalid unicode").to_string();
+        let metadata = entry.metadata()?;
+        let is_dir = metadata.is_dir();
+        let size = if is_dir { 0 } else { metadata.len() };
+        let full_path = format!("{}/{}", current_path, name);
+        let href = encode(&full_path, NON_ALPHANUMERIC).into_owned();
+        entries.push(DirEntryInfo {
+            name,
+            is_dir,
+            size,
+            href,
+        });
+    }
+    Ok(entries)
+}
+
+/// Sorts entries directories-first, then alphabetically (case-insensitive)
+/// within each group. This is the only order the listing is rendered in; the
+/// table's column headers are plain text and cannot re-sort it.
+fn sort_dir_entries(entries: &mut [DirEntryInfo]) {
+    entries.sort_by(|a, b| {
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+}
+
+/// Formats a byte count as a human-readable size (B/KiB/MiB/GiB).
+fn human_readable_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[unit])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Escapes text for safe inclusion in HTML, since file names are
+/// attacker-controlled (a name containing `<`, `>`, `&` or quotes would
+/// otherwise be written straight into the markup).
+fn html_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_dir_listing_html(
+    current_dir_display: &str,
+    parent_href: Option<&str>,
+    entries: &[DirEntryInfo],
+) -> Vec<u8> {
+    let mut listing = Vec::new();
+    listing.extend_from_slice(b"<html><head><meta charset=\"utf-8\"/></head><body>");
+
+    listing.extend_from_slice(b"<h1>Directory Listing</h1>");
+    listing.extend_from_slice(b"<p>Current directory: ");
+    listing.extend_from_slice(html_escape(current_dir_display).as_bytes());
+    listing.extend_from_slice(b"</p>");
+
+    if let Some(parent_href) = parent_href {
+        listing.extend_from_slice(b"<p><a href=\"");
+        listing.extend_from_slice(parent_href.as_bytes());
+        listing.extend_from_slice(b"\">Up One Level</a></p>");
+    }
+
+    listing.extend_from_slice(
+        b"<table><thead><tr><th>Name</th><th>Size</th><th>Type</th></tr></thead><tbody>",
+    );
+    for entry in entries {
+        let icon = if entry.is_dir { "\u{1F4C1}" } else { "\u{1F4C4}" };
+        let display_name = if entry.is_dir {
+            format!("{}/", entry.name)
+        } else {
+            entry.name.clone()
+        };
+        let size = if entry.is_dir {
+            "-".to_string()
+        } else {
+            human_readable_size(entry.size)
+        };
+        let kind = if entry.is_dir { "Directory" } else { "File" };
+
+        listing.extend_from_slice(b"<tr><td><a href=\"");
+        listing.extend_from_slice(entry.href.as_bytes());
+        listing.extend_from_slice(b"\">");
+        listing.extend_from_slice(icon.as_bytes());
+        listing.extend_from_slice(b" ");
+        listing.extend_from_slice(html_escape(&display_name).as_bytes());
+        listing.extend_from_slice(b"</a></td><td>");
+        listing.extend_from_slice(size.as_bytes());
+        listing.extend_from_slice(b"</td><td>");
+        listing.extend_from_slice(kind.as_bytes());
+        listing.extend_from_slice(b"</td></tr>");
+    }
+    listing.extend_from_slice(b"</tbody></table></body></html>");
+
+    listing
+}
+
+fn render_dir_listing_json(entries: &[DirEntryInfo]) -> Vec<u8> {
+    let mut json = String::from("[");
+    for (i, entry) in entries.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        json.push_str(&format!(
+            "{{\"name\":\"{}\",\"is_dir\":{},\"size\":{},\"href\":\"{}\"}}",
+            json_escape(&entry.name),
+            entry.is_dir,
+            entry.size,
+            json_escape(&entry.href),
+        ));
+    }
+    json.push(']');
+    json.into_bytes()
+}
+
+fn json_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_markdown_strips_raw_html() {
+        let rendered = render_markdown("hello <script>alert(1)</script> world");
+        assert!(!rendered.contains("<script>"));
+    }
+
+    #[test]
+    fn render_markdown_keeps_generated_markup() {
+        let rendered = render_markdown("# Title\n\n- one\n- two");
+        assert!(rendered.contains("<h1>Title</h1>"));
+        assert!(rendered.contains("<li>one</li>"));
+    }
+
+    #[test]
+    fn parse_range_full_bounds() {
+        assert_eq!(parse_range("bytes=0-99", 100), Ok(Some((0, 99))));
+    }
+
+    #[test]
+    fn parse_range_from_offset_to_eof() {
+        assert_eq!(parse_range("bytes=50-", 100), Ok(Some((50, 99))));
+    }
+
+    #[test]
+    fn parse_range_suffix() {
+        assert_eq!(parse_range("bytes=-10", 100), Ok(Some((90, 99))));
+    }
+
+    #[test]
+    fn parse_range_suffix_larger_than_file_is_clamped_to_start() {
+        assert_eq!(parse_range("bytes=-1000", 100), Ok(Some((0, 99))));
+    }
+
+    #[test]
+    fn parse_range_end_is_clamped_to_len_minus_one() {
+        assert_eq!(parse_range("bytes=0-1000", 100), Ok(Some((0, 99))));
+    }
+
+    #[test]
+    fn parse_range_start_past_len_is_unsatisfiable() {
+        assert_eq!(parse_range("bytes=100-200", 100), Err(()));
+    }
+
+    #[test]
+    fn parse_range_start_greater_than_end_is_unsatisfiable() {
+        assert_eq!(parse_range("bytes=50-10", 100), Err(()));
+    }
+
+    #[test]
+    fn parse_range_zero_suffix_is_unsatisfiable() {
+        assert_eq!(parse_range("bytes=-0", 100), Err(()));
+    }
+
+    #[test]
+    fn parse_range_non_byte_unit_is_not_a_range() {
+        assert_eq!(parse_range("items=0-5", 100), Ok(None));
+    }
+
+    #[test]
+    fn parse_range_malformed_spec_is_unsatisfiable() {
+        assert_eq!(parse_range("bytes=abc", 100), Err(()));
+    }
+
+    #[test]
+    fn format_http_date_matches_known_epoch_date() {
+        // 1994-11-06 08:49:37 UTC, the example date from RFC 7231.
+        assert_eq!(
+            format_http_date(784111777),
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        );
+    }
+
+    #[test]
+    fn format_http_date_epoch_zero_is_a_thursday() {
+        assert_eq!(format_http_date(0), "Thu, 01 Jan 1970 00:00:00 GMT");
+    }
+
+    #[test]
+    fn parse_http_date_round_trips_through_format_http_date() {
+        let secs = 1_700_000_000;
+        assert_eq!(parse_http_date(&format_http_date(secs)), Some(secs));
+    }
+
+    #[test]
+    fn parse_http_date_rejects_garbage() {
+        assert_eq!(parse_http_date("not a date"), None);
+    }
+
+    #[test]
+    fn is_not_modified_prefers_if_none_match_over_if_modified_since() {
+        let mut headers = std::collections::HashMap::new();
+        headers.insert("if-none-match".to_string(), "W/\"other-etag\"".to_string());
+        // A stale If-Modified-Since would normally trigger a 304, but a
+        // present, non-matching If-None-Match must take precedence.
+        headers.insert(
+            "if-modified-since".to_string(),
+            format_http_date(2_000_000_000),
+        );
+        assert!(!is_not_modified(&headers, "W/\"123-456\"", 1_000_000_000));
+    }
+
+    #[test]
+    fn is_not_modified_falls_back_to_if_modified_since() {
+        let mut headers = std::collections::HashMap::new();
+        headers.insert(
+            "if-modified-since".to_string(),
+            format_http_date(2_000_000_000),
+        );
+        assert!(is_not_modified(&headers, "W/\"123-456\"", 1_000_000_000));
+    }
+
+    #[test]
+    fn is_not_modified_matches_etag() {
+        let mut headers = std::collections::HashMap::new();
+        headers.insert("if-none-match".to_string(), "W/\"123-456\"".to_string());
+        assert!(is_not_modified(&headers, "W/\"123-456\"", 1_000_000_000));
+    }
+
+    #[test]
+    fn negotiate_encoding_prefers_gzip_over_deflate() {
+        assert!(matches!(
+            negotiate_encoding("deflate, gzip"),
+            Some(ContentEncoding::Gzip)
+        ));
+    }
+
+    #[test]
+    fn negotiate_encoding_falls_back_to_deflate() {
+        assert!(matches!(
+            negotiate_encoding("deflate"),
+            Some(ContentEncoding::Deflate)
+        ));
+    }
+
+    #[test]
+    fn negotiate_encoding_none_offered() {
+        assert!(negotiate_encoding("br").is_none());
+    }
+
+    #[test]
+    fn negotiate_encoding_rejects_q_zero_gzip() {
+        // A q=0 coding is explicitly unacceptable to the client (RFC 7231 §5.3.1).
+        assert!(negotiate_encoding("gzip;q=0").is_none());
+    }
+
+    #[test]
+    fn negotiate_encoding_falls_back_past_rejected_gzip() {
+        assert!(matches!(
+            negotiate_encoding("gzip;q=0, deflate"),
+            Some(ContentEncoding::Deflate)
+        ));
+    }
+
+    #[test]
+    fn negotiate_encoding_accepts_nonzero_q_gzip() {
+        assert!(matches!(
+            negotiate_encoding("gzip;q=0.5"),
+            Some(ContentEncoding::Gzip)
+        ));
+    }
+
+    #[test]
+    fn json_escape_plain_text_is_unchanged() {
+        assert_eq!(json_escape("file.txt"), "file.txt");
+    }
+
+    #[test]
+    fn json_escape_quotes_and_backslashes() {
+        assert_eq!(json_escape("a\"b\\c"), "a\\\"b\\\\c");
+    }
+
+    #[test]
+    fn json_escape_control_characters() {
+        assert_eq!(json_escape("a\nb\tc\rd"), "a\\nb\\tc\\rd");
+        assert_eq!(json_escape("\u{1}"), "\\u0001");
+    }
+
+    #[test]
+    fn render_dir_listing_json_shape() {
+        let entries = vec![DirEntryInfo {
+            name: "weird \"name\".txt".to_string(),
+            is_dir: false,
+            size: 42,
+            href: "weird%20%22name%22.txt".to_string(),
+        }];
+        let json = String::from_utf8(render_dir_listing_json(&entries)).unwrap();
+        assert_eq!(
+            json,
+            "[{\"name\":\"weird \\\"name\\\".txt\",\"is_dir\":false,\"size\":42,\"href\":\"weird%20%22name%22.txt\"}]"
+        );
+    }
+
+    #[test]
+    fn html_escape_escapes_all_special_characters() {
+        assert_eq!(
+            html_escape("<script>&\"'"),
+            "&lt;script&gt;&amp;&quot;&#39;"
+        );
+    }
+
+    #[test]
+    fn html_escape_plain_text_is_unchanged() {
+        assert_eq!(html_escape("plain-file.txt"), "plain-file.txt");
+    }
+
+    #[test]
+    fn human_readable_size_scales_units() {
+        assert_eq!(human_readable_size(512), "512 B");
+        assert_eq!(human_readable_size(2048), "2.0 KiB");
+        assert_eq!(human_readable_size(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(human_readable_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn sort_dir_entries_puts_directories_first_then_alphabetical() {
+        let mut entries = vec![
+            DirEntryInfo {
+                name: "zeta.txt".to_string(),
+                is_dir: false,
+                size: 0,
+                href: String::new(),
+            },
+            DirEntryInfo {
+                name: "Beta".to_string(),
+                is_dir: true,
+                size: 0,
+                href: String::new(),
+            },
+            DirEntryInfo {
+                name: "alpha.txt".to_string(),
+                is_dir: false,
+                size: 0,
+                href: String::new(),
+            },
+            DirEntryInfo {
+                name: "alpha-dir".to_string(),
+                is_dir: true,
+                size: 0,
+                href: String::new(),
+            },
+        ];
+        sort_dir_entries(&mut entries);
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["alpha-dir", "Beta", "alpha.txt", "zeta.txt"]);
+    }
+}